@@ -0,0 +1,83 @@
+//! Offloading genuinely blocking work to Seastar's blocking thread pool.
+//!
+//! [`crate::spawn`] only helps futures that cooperatively yield; a CPU-bound
+//! computation or a syscall that can actually block would stall the whole
+//! shard. [`spawn_blocking`] instead runs the closure off the reactor
+//! thread, on Seastar's `thread_pool`/syscall mechanism, and delivers the
+//! result back through the same oneshot-channel pattern [`crate::spawn`]
+//! uses.
+
+use futures::channel::oneshot;
+use futures::Future;
+use std::error::Error;
+use std::ffi::c_void;
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+extern "C" {
+    // Dispatches `func(data)` onto Seastar's blocking thread pool, off the
+    // reactor thread. `func` is responsible for freeing `data`.
+    fn seastar_rs_spawn_blocking(func: unsafe extern "C" fn(*mut c_void), data: *mut c_void);
+}
+
+struct BlockingJob<F, R> {
+    func: F,
+    sender: oneshot::Sender<std::thread::Result<R>>,
+}
+
+unsafe extern "C" fn run_blocking<F, R>(data: *mut c_void)
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let BlockingJob { func, sender } = *Box::from_raw(data as *mut BlockingJob<F, R>);
+    let result = catch_unwind(AssertUnwindSafe(func));
+    let _ = sender.send(result);
+}
+
+/// Runs `f` on Seastar's blocking thread pool and resolves once it's done.
+///
+/// Panics inside `f` are captured and resumed on the awaiting side, the same
+/// way [`crate::spawn`] propagates them. If the runtime is shutting down
+/// before `f` gets a chance to run, the returned future resolves to
+/// `Err(BlockingError::Shutdown)` instead of hanging forever.
+pub fn spawn_blocking<F, R>(f: F) -> impl Future<Output = Result<R, BlockingError>>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    let job = Box::new(BlockingJob { func: f, sender });
+    let data = Box::into_raw(job) as *mut c_void;
+    unsafe {
+        seastar_rs_spawn_blocking(run_blocking::<F, R>, data);
+    }
+    async move {
+        match receiver.await {
+            Ok(Ok(v)) => Ok(v),
+            Ok(Err(panic)) => std::panic::resume_unwind(panic),
+            // The sender was dropped without sending - the runtime is
+            // shutting down and the closure never ran.
+            Err(_canceled) => Err(BlockingError::Shutdown),
+        }
+    }
+}
+
+/// Error returned by [`spawn_blocking`] when the closure never got to run.
+#[derive(Debug)]
+pub enum BlockingError {
+    /// The runtime was shutting down before the closure could be dispatched.
+    Shutdown,
+}
+
+impl fmt::Display for BlockingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockingError::Shutdown => {
+                write!(f, "the runtime is shutting down, blocking closure was not run")
+            }
+        }
+    }
+}
+
+impl Error for BlockingError {}
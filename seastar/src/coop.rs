@@ -0,0 +1,181 @@
+//! Automatic cooperative-budget yielding, layered on top of [`need_preempt`].
+//!
+//! Relying on developers to sprinkle [`maybe_yield`](crate::maybe_yield)
+//! calls over long await chains is error-prone: a leaf resource (a channel,
+//! an I/O wrapper, a `submit_to` completion) has no idea how deep the chain
+//! above it is. Instead, each task gets a fixed quota of "may complete a
+//! leaf operation" tokens per poll; once the quota runs out the task is
+//! forced to yield back to the reactor regardless of what it's awaiting,
+//! bounding how long a single task can hog a shard.
+
+use std::cell::Cell;
+use std::task::{Context, Poll};
+
+use crate::need_preempt;
+
+const BUDGET_PER_POLL: u32 = 128;
+
+thread_local! {
+    static BUDGET: Cell<u32> = const { Cell::new(BUDGET_PER_POLL) };
+    static UNCONSTRAINED_DEPTH: Cell<u32> = const { Cell::new(0) };
+}
+
+// Called by the FFI poll trampoline at the start of every `poll_fn`
+// invocation, i.e. once per top-level task poll.
+pub(crate) fn reset_budget() {
+    let budget = if need_preempt() { 0 } else { BUDGET_PER_POLL };
+    BUDGET.with(|b| b.set(budget));
+}
+
+/// A leaf future should call this before polling its inner operation, as in:
+///
+/// ```ignore
+/// let coop = match coop::poll_proceed(cx) {
+///     Poll::Ready(coop) => coop,
+///     Poll::Pending => return Poll::Pending,
+/// };
+/// match self.inner.poll(cx) {
+///     Poll::Ready(v) => {
+///         coop.made_progress();
+///         Poll::Ready(v)
+///     }
+///     Poll::Pending => Poll::Pending,
+/// }
+/// ```
+///
+/// If the task's budget isn't exhausted, this decrements it and returns
+/// `Poll::Ready`, carrying a guard to be told about afterwards whether the
+/// operation actually produced a value. If the budget is already exhausted,
+/// this re-arms the waker and returns `Poll::Pending`, forcing the task to
+/// yield while preserving its place - the caller should propagate that
+/// `Pending` without touching its own inner future any further this poll.
+pub fn poll_proceed(cx: &mut Context<'_>) -> Poll<RestoreOnPending> {
+    if UNCONSTRAINED_DEPTH.with(|d| d.get() > 0) {
+        return Poll::Ready(RestoreOnPending {
+            decremented: false,
+            made_progress: Cell::new(false),
+        });
+    }
+
+    let had_budget = BUDGET.with(|b| {
+        let remaining = b.get();
+        if remaining > 0 {
+            b.set(remaining - 1);
+            true
+        } else {
+            false
+        }
+    });
+
+    if had_budget {
+        Poll::Ready(RestoreOnPending {
+            decremented: true,
+            made_progress: Cell::new(false),
+        })
+    } else {
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Returned by [`poll_proceed`]. Call [`RestoreOnPending::made_progress`]
+/// once it's known whether the guarded operation actually completed; if it
+/// turned out pending after all, the consumed unit of budget is put back on
+/// drop, since only operations that do real work should count against it.
+pub struct RestoreOnPending {
+    decremented: bool,
+    made_progress: Cell<bool>,
+}
+
+impl RestoreOnPending {
+    #[inline]
+    pub fn made_progress(&self) {
+        self.made_progress.set(true);
+    }
+}
+
+impl Drop for RestoreOnPending {
+    fn drop(&mut self) {
+        if self.decremented && !self.made_progress.get() {
+            BUDGET.with(|b| b.set(b.get() + 1));
+        }
+    }
+}
+
+/// Runs `fut`, and anything it polls, without being subject to the
+/// cooperative budget - an escape hatch for subtrees that must not be
+/// forced to yield (e.g. because they're already bounded some other way).
+pub fn unconstrained<F>(fut: F) -> Unconstrained<F> {
+    Unconstrained { inner: fut }
+}
+
+pub struct Unconstrained<F> {
+    inner: F,
+}
+
+impl<F: std::future::Future> std::future::Future for Unconstrained<F> {
+    type Output = F::Output;
+
+    fn poll(self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        UNCONSTRAINED_DEPTH.with(|d| d.set(d.get() + 1));
+        let _guard = DepthGuard;
+        // SAFETY: structural projection; `inner` is never moved out of.
+        let inner = unsafe { self.map_unchecked_mut(|s| &mut s.inner) };
+        inner.poll(cx)
+    }
+}
+
+// Undoes the depth increment above on the way out of `poll`, including via
+// an unwinding panic from `inner.poll` - otherwise a single panicking task
+// would leave `UNCONSTRAINED_DEPTH` permanently elevated and silently
+// disable cooperative-budget enforcement for the rest of the shard.
+struct DepthGuard;
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        UNCONSTRAINED_DEPTH.with(|d| d.set(d.get() - 1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poll_proceed_decrements_and_refunds_on_drop_without_progress() {
+        BUDGET.with(|b| b.set(1));
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        match poll_proceed(&mut cx) {
+            Poll::Ready(guard) => drop(guard),
+            Poll::Pending => panic!("budget should not be exhausted yet"),
+        }
+        assert_eq!(BUDGET.with(Cell::get), 1);
+
+        match poll_proceed(&mut cx) {
+            Poll::Ready(guard) => guard.made_progress(),
+            Poll::Pending => panic!("budget should not be exhausted yet"),
+        }
+        assert_eq!(BUDGET.with(Cell::get), 0);
+        assert!(poll_proceed(&mut cx).is_pending());
+    }
+
+    #[test]
+    fn unconstrained_depth_is_restored_even_if_inner_panics() {
+        struct PanicsOnPoll;
+        impl std::future::Future for PanicsOnPoll {
+            type Output = ();
+            fn poll(self: std::pin::Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+                panic!("boom");
+            }
+        }
+
+        BUDGET.with(|b| b.set(BUDGET_PER_POLL));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            futures::executor::block_on(unconstrained(PanicsOnPoll));
+        }));
+        assert!(result.is_err());
+        assert_eq!(UNCONSTRAINED_DEPTH.with(Cell::get), 0);
+    }
+}
@@ -68,8 +68,18 @@ impl Logger {
     #[inline]
     pub fn log(&self, level: LogLevel, args: Arguments<'_>) {
         let core = self.core.get_or_init(|| ffi::new_logger(self.name));
-        let ctx = FormatCtx { args };
-        ffi::log(&core, level as u32, &ctx);
+        match crate::current_task_metadata().and_then(|m| m.name()) {
+            Some(task_name) => {
+                let ctx = FormatCtx {
+                    args: format_args!("[{task_name}] {args}"),
+                };
+                ffi::log(&core, level as u32, &ctx);
+            }
+            None => {
+                let ctx = FormatCtx { args };
+                ffi::log(&core, level as u32, &ctx);
+            }
+        }
     }
 
     #[inline]
@@ -0,0 +1,132 @@
+//! Per-shard reactor and task-spawning metrics, for diagnosing stalls and
+//! load imbalance across shards in a thread-per-core deployment.
+
+use std::cell::Cell;
+
+#[cxx::bridge(namespace = "seastar_ffi::metrics")]
+mod ffi {
+    unsafe extern "C++" {
+        include!("seastar-rs/src/reactor_metrics.hh");
+
+        /// Total number of tasks polled by the local reactor since startup.
+        fn tasks_polled() -> u64;
+        /// Total number of tasks scheduled on the local reactor.
+        fn tasks_scheduled() -> u64;
+        /// Number of polls that returned without completing a task.
+        fn polls_returned_pending() -> u64;
+        /// Number of busy-wait iterations performed while the reactor had no
+        /// other work to do.
+        fn busy_wait_iterations() -> u64;
+        /// Current number of tasks sitting in the local run queue.
+        fn queue_depth() -> u64;
+    }
+}
+
+mod ffi_shard {
+    extern "C" {
+        pub(super) fn this_shard() -> u32;
+    }
+}
+
+fn this_shard() -> u32 {
+    unsafe { ffi_shard::this_shard() }
+}
+
+/// A snapshot of a single shard's reactor metrics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShardMetrics {
+    pub shard_id: u32,
+    pub tasks_polled: u64,
+    pub tasks_scheduled: u64,
+    pub polls_returned_pending: u64,
+    pub busy_wait_iterations: u64,
+    pub queue_depth: u64,
+}
+
+fn local_shard_metrics() -> ShardMetrics {
+    ShardMetrics {
+        shard_id: this_shard(),
+        tasks_polled: ffi::tasks_polled(),
+        tasks_scheduled: ffi::tasks_scheduled(),
+        polls_returned_pending: ffi::polls_returned_pending(),
+        busy_wait_iterations: ffi::busy_wait_iterations(),
+        queue_depth: ffi::queue_depth(),
+    }
+}
+
+/// Collects a [`ShardMetrics`] snapshot from every shard.
+///
+/// The result is ordered by `shard_id` and can be used to compute totals or
+/// spot per-shard imbalance (e.g. one reactor accumulating a much deeper run
+/// queue than its peers).
+pub async fn reactor_metrics() -> Vec<ShardMetrics> {
+    crate::native::sharded::invoke_on_all_shards(|| async { local_shard_metrics() }).await
+}
+
+// Unlike `ShardMetrics` above, these are maintained entirely on the Rust
+// side by the spawn machinery in `task.rs`/`preempt.rs`: no atomics on the
+// hot path, just per-shard thread-locals bumped as tasks come and go.
+thread_local! {
+    static TASKS_SPAWNED: Cell<u64> = const { Cell::new(0) };
+    static TASKS_ALIVE: Cell<u64> = const { Cell::new(0) };
+    static SUBMIT_TO_SENT: Cell<u64> = const { Cell::new(0) };
+    static SUBMIT_TO_RECEIVED: Cell<u64> = const { Cell::new(0) };
+    static FORCED_YIELDS: Cell<u64> = const { Cell::new(0) };
+}
+
+pub(crate) fn record_task_spawned() {
+    TASKS_SPAWNED.with(|c| c.set(c.get() + 1));
+    TASKS_ALIVE.with(|c| c.set(c.get() + 1));
+}
+
+pub(crate) fn record_task_finished() {
+    TASKS_ALIVE.with(|c| c.set(c.get().saturating_sub(1)));
+}
+
+pub(crate) fn record_submit_to_sent() {
+    SUBMIT_TO_SENT.with(|c| c.set(c.get() + 1));
+}
+
+pub(crate) fn record_submit_to_received() {
+    SUBMIT_TO_RECEIVED.with(|c| c.set(c.get() + 1));
+}
+
+pub(crate) fn record_forced_yield() {
+    FORCED_YIELDS.with(|c| c.set(c.get() + 1));
+}
+
+/// A snapshot of a single shard's task-spawning metrics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpawnMetrics {
+    pub shard_id: u32,
+    /// Total number of tasks spawned on this shard via `spawn`/`submit_to`,
+    /// since startup.
+    pub tasks_spawned: u64,
+    /// Number of those tasks that haven't finished (or been cancelled) yet.
+    pub tasks_alive: u64,
+    /// Number of `submit_to` invocations sent out from this shard.
+    pub submit_to_sent: u64,
+    /// Number of `submit_to` invocations that started running on this
+    /// shard, whether sent from here or elsewhere.
+    pub submit_to_received: u64,
+    /// Number of times a task on this shard was forced to yield via
+    /// `need_preempt`/`maybe_yield`.
+    pub forced_yields: u64,
+}
+
+/// Returns a snapshot of this shard's task-spawning metrics.
+pub fn shard() -> SpawnMetrics {
+    SpawnMetrics {
+        shard_id: this_shard(),
+        tasks_spawned: TASKS_SPAWNED.with(Cell::get),
+        tasks_alive: TASKS_ALIVE.with(Cell::get),
+        submit_to_sent: SUBMIT_TO_SENT.with(Cell::get),
+        submit_to_received: SUBMIT_TO_RECEIVED.with(Cell::get),
+        forced_yields: FORCED_YIELDS.with(Cell::get),
+    }
+}
+
+/// Collects a [`SpawnMetrics`] snapshot from every shard.
+pub async fn all_shards() -> Vec<SpawnMetrics> {
+    crate::native::sharded::invoke_on_all_shards(|| async { shard() }).await
+}
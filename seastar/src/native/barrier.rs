@@ -0,0 +1,155 @@
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+/// A shard-local rendezvous point for a fixed number of tasks.
+///
+/// Unlike [`crate::native::PhasedBarrier`], which tracks an open-ended stream
+/// of in-progress operations, a `Barrier` makes exactly `n` callers of
+/// [`Barrier::wait`] block until all `n` have arrived, then releases them
+/// together - and is reusable for the next round immediately afterwards.
+pub struct Barrier {
+    n: usize,
+    state: RefCell<State>,
+}
+
+struct State {
+    arrived: usize,
+    generation: u64,
+    wakers: Vec<Rc<Cell<Option<Waker>>>>,
+}
+
+impl Barrier {
+    /// Creates a barrier that releases once `n` tasks have called
+    /// [`Barrier::wait`].
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            state: RefCell::new(State {
+                arrived: 0,
+                generation: 0,
+                wakers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Blocks until `n` tasks (including this one) have called `wait` on
+    /// this barrier, then releases all of them at once.
+    ///
+    /// Exactly one of the released callers gets back a
+    /// [`BarrierWaitResult`] for which [`BarrierWaitResult::is_leader`] is
+    /// `true` - the rest get `false`. The barrier can then be awaited again
+    /// for the next round.
+    pub fn wait(&self) -> Wait<'_> {
+        Wait {
+            barrier: self,
+            waiter: None,
+        }
+    }
+}
+
+/// A future returned by [`Barrier::wait`].
+pub struct Wait<'a> {
+    barrier: &'a Barrier,
+    // The generation we're waiting to see pass, and our own waker slot, once
+    // registered.
+    waiter: Option<(u64, Rc<Cell<Option<Waker>>>)>,
+}
+
+impl<'a> Future for Wait<'a> {
+    type Output = BarrierWaitResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut state = this.barrier.state.borrow_mut();
+
+        if let Some((generation, waker_slot)) = &this.waiter {
+            if state.generation != *generation {
+                return Poll::Ready(BarrierWaitResult { is_leader: false });
+            }
+            waker_slot.set(Some(cx.waker().clone()));
+            return Poll::Pending;
+        }
+
+        let my_generation = state.generation;
+        state.arrived += 1;
+        if state.arrived == this.barrier.n {
+            // We're the last to arrive: release everybody (including
+            // ourselves) and reset for the next round.
+            state.arrived = 0;
+            state.generation = state.generation.wrapping_add(1);
+            let wakers = std::mem::take(&mut state.wakers);
+            drop(state);
+            for waker_slot in wakers {
+                if let Some(waker) = waker_slot.take() {
+                    waker.wake();
+                }
+            }
+            return Poll::Ready(BarrierWaitResult { is_leader: true });
+        }
+
+        let waker_slot = Rc::new(Cell::new(Some(cx.waker().clone())));
+        state.wakers.push(Rc::clone(&waker_slot));
+        this.waiter = Some((my_generation, waker_slot));
+        Poll::Pending
+    }
+}
+
+impl<'a> Drop for Wait<'a> {
+    fn drop(&mut self) {
+        if let Some((generation, waker_slot)) = self.waiter.take() {
+            let mut state = self.barrier.state.borrow_mut();
+            // If the generation already moved on, this round released
+            // (possibly with us as the leader) before we got dropped, so
+            // there's nothing to undo.
+            if state.generation == generation {
+                state.arrived -= 1;
+                state.wakers.retain(|w| !Rc::ptr_eq(w, &waker_slot));
+            }
+        }
+    }
+}
+
+/// The result of waiting on a [`Barrier`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BarrierWaitResult {
+    is_leader: bool,
+}
+
+impl BarrierWaitResult {
+    /// Returns `true` for exactly one of the tasks released by a given
+    /// round of the barrier.
+    #[inline]
+    pub fn is_leader(&self) -> bool {
+        self.is_leader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dropping_a_waiter_does_not_inflate_the_next_round() {
+        let barrier = Barrier::new(2);
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        // One waiter parks, then is dropped before the barrier fills - it
+        // must not leave a phantom arrival behind for the next round.
+        {
+            let wait = barrier.wait();
+            futures::pin_mut!(wait);
+            assert!(wait.as_mut().poll(&mut cx).is_pending());
+        }
+
+        let wait1 = barrier.wait();
+        let wait2 = barrier.wait();
+        futures::pin_mut!(wait1);
+        futures::pin_mut!(wait2);
+        assert!(wait1.as_mut().poll(&mut cx).is_pending());
+        assert!(wait2.as_mut().poll(&mut cx).is_ready());
+    }
+}
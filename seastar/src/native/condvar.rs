@@ -0,0 +1,147 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+/// A shard-local condition variable for tasks that need to wait on an
+/// arbitrary predicate over state shared via `Rc<RefCell<_>>`.
+///
+/// Because this runs on a single-threaded cooperative executor, there is no
+/// associated mutex to release while waiting - the caller simply re-checks
+/// its predicate after each wakeup and calls [`Condvar::wait`] again if it's
+/// still false. [`Condvar::wait_until`] does exactly that for you.
+#[derive(Default)]
+pub struct Condvar {
+    waiters: RefCell<VecDeque<Rc<WaiterState>>>,
+}
+
+struct WaiterState {
+    waker: Cell<Option<Waker>>,
+    notified: Cell<bool>,
+}
+
+impl Condvar {
+    /// Creates a new, empty condition variable.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Waits to be woken up by [`Condvar::notify_one`] or
+    /// [`Condvar::notify_all`].
+    ///
+    /// Resolves only after an actual notification, never spuriously, but
+    /// callers should still re-check their predicate afterwards since
+    /// another task may have changed the state first - see
+    /// [`Condvar::wait_until`] for a convenience loop that does this.
+    #[inline]
+    pub fn wait(&self) -> Wait<'_> {
+        Wait {
+            condvar: self,
+            waiter: None,
+        }
+    }
+
+    /// Waits until `pred` returns `true`, re-checking it after every
+    /// notification.
+    pub async fn wait_until<F>(&self, mut pred: F)
+    where
+        F: FnMut() -> bool,
+    {
+        while !pred() {
+            self.wait().await;
+        }
+    }
+
+    /// Wakes the oldest waiting task, if any.
+    pub fn notify_one(&self) {
+        if let Some(waiter) = self.waiters.borrow_mut().pop_front() {
+            waiter.notified.set(true);
+            if let Some(waker) = waiter.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Wakes every currently waiting task.
+    pub fn notify_all(&self) {
+        for waiter in self.waiters.borrow_mut().drain(..) {
+            waiter.notified.set(true);
+            if let Some(waker) = waiter.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// A future returned by [`Condvar::wait`].
+pub struct Wait<'a> {
+    condvar: &'a Condvar,
+    waiter: Option<Rc<WaiterState>>,
+}
+
+impl<'a> Future for Wait<'a> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if let Some(waiter) = &this.waiter {
+            if waiter.notified.get() {
+                return Poll::Ready(());
+            }
+            waiter.waker.set(Some(cx.waker().clone()));
+            return Poll::Pending;
+        }
+
+        let waiter = Rc::new(WaiterState {
+            waker: Cell::new(Some(cx.waker().clone())),
+            notified: Cell::new(false),
+        });
+        this.condvar.waiters.borrow_mut().push_back(Rc::clone(&waiter));
+        this.waiter = Some(waiter);
+        Poll::Pending
+    }
+}
+
+impl<'a> Drop for Wait<'a> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            if !waiter.notified.get() {
+                self.condvar
+                    .waiters
+                    .borrow_mut()
+                    .retain(|w| !Rc::ptr_eq(w, &waiter));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notify_one_wakes_the_oldest_waiter_first() {
+        let condvar = Condvar::new();
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let wait1 = condvar.wait();
+        let wait2 = condvar.wait();
+        futures::pin_mut!(wait1);
+        futures::pin_mut!(wait2);
+
+        assert!(wait1.as_mut().poll(&mut cx).is_pending());
+        assert!(wait2.as_mut().poll(&mut cx).is_pending());
+
+        condvar.notify_one();
+        assert!(wait1.as_mut().poll(&mut cx).is_ready());
+        assert!(wait2.as_mut().poll(&mut cx).is_pending());
+
+        condvar.notify_one();
+        assert!(wait2.as_mut().poll(&mut cx).is_ready());
+    }
+}
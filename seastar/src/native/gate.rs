@@ -1,4 +1,4 @@
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::error::Error;
 use std::fmt;
 use std::ops::Deref;
@@ -11,7 +11,12 @@ pub struct Gate<T> {
 
     use_count: Cell<usize>,
     closed: Cell<bool>,
-    waker: Cell<Option<Waker>>,
+    // Every task awaiting close() registers a waker slot here, once, the
+    // first time it's polled while still pending - subsequent polls of the
+    // same call just update the slot in place rather than pushing again. A
+    // Vec is fine otherwise: close() is rare and the registry is drained in
+    // one shot once the use count reaches zero.
+    wakers: RefCell<Vec<Rc<Cell<Option<Waker>>>>>,
 }
 
 impl<T> Gate<T> {
@@ -21,7 +26,7 @@ impl<T> Gate<T> {
             inner,
             use_count: Cell::new(0),
             closed: Cell::new(false),
-            waker: Cell::new(None),
+            wakers: RefCell::new(Vec::new()),
         }
     }
 
@@ -57,19 +62,30 @@ impl<T> Gate<T> {
         }
     }
 
+    /// Closes the gate, preventing any future calls to [`Gate::enter`] /
+    /// [`Gate::enter_owned`] from succeeding, and waits for all operations
+    /// already in progress to finish.
+    ///
+    /// It is fine for several tasks to `await` this concurrently, or for the
+    /// same task to call it more than once (e.g. from two different places
+    /// that both want to make sure the gate is closed): every such caller
+    /// registers its own waker and all of them resolve once the last
+    /// in-progress operation finishes.
     #[inline]
     pub async fn close(&self) {
-        if self.is_closed() {
-            panic!("attempted to close the gate for the second time");
-        }
-
         self.closed.set(true);
 
+        let waker_slot: Rc<Cell<Option<Waker>>> = Rc::new(Cell::new(None));
+        let mut registered = false;
         std::future::poll_fn(|cx| {
             if self.use_count.get() == 0 {
                 Poll::Ready(())
             } else {
-                self.waker.set(Some(cx.waker().clone()));
+                waker_slot.set(Some(cx.waker().clone()));
+                if !registered {
+                    self.wakers.borrow_mut().push(Rc::clone(&waker_slot));
+                    registered = true;
+                }
                 Poll::Pending
             }
         })
@@ -84,10 +100,11 @@ impl<T> Gate<T> {
         let new_count = self.use_count.get() - 1;
         self.use_count.set(new_count);
         if self.is_closed() && new_count == 0 {
-            // The gate was closed but there were some references to it,
-            // therefore the close() future stored a waker and it's safe
-            // to unwrap here.
-            self.waker.take().unwrap().wake();
+            for waker_slot in self.wakers.borrow_mut().drain(..) {
+                if let Some(waker) = waker_slot.take() {
+                    waker.wake();
+                }
+            }
         }
     }
 }
@@ -3,6 +3,8 @@ use std::future::Future;
 use std::rc::Rc;
 use std::task::{Poll, Waker};
 
+type WakerRegistry = RefCell<Vec<Rc<Cell<Option<Waker>>>>>;
+
 #[derive(Default)]
 pub struct PhasedBarrier {
     current_stage: RefCell<Rc<PhasedBarrierStage>>,
@@ -73,11 +75,24 @@ impl PhasedBarrier {
         // longer than necessary.
         let old_stage = Rc::downgrade(&old_stage);
 
+        let waker_slot: Rc<Cell<Option<Waker>>> = Rc::new(Cell::new(None));
+        let mut registered = false;
         std::future::poll_fn(move |cx| {
             match old_stage.upgrade() {
                 Some(stage) => {
-                    // Stage still alive - register the waker
-                    stage.wake_on_drop.0.set(Some(cx.waker().clone()));
+                    // Stage still alive - register our waker slot, once.
+                    // Several callers can be polling the future returned by
+                    // the same advance() concurrently (or different
+                    // advance() calls can end up waiting on the same
+                    // stage), so every one of them must be remembered here,
+                    // not just the most recent; subsequent polls of this
+                    // same future just update the slot in place rather than
+                    // pushing again.
+                    waker_slot.set(Some(cx.waker().clone()));
+                    if !registered {
+                        stage.wake_on_drop.0.borrow_mut().push(Rc::clone(&waker_slot));
+                        registered = true;
+                    }
                     Poll::Pending
                 }
                 None => {
@@ -100,13 +115,15 @@ impl PhasedBarrier {
 pub struct Operation(Rc<PhasedBarrierStage>);
 
 #[derive(Default)]
-struct WakeOnDrop(Cell<Option<Waker>>);
+struct WakeOnDrop(WakerRegistry);
 
 impl Drop for WakeOnDrop {
     #[inline]
     fn drop(&mut self) {
-        if let Some(waker) = self.0.take() {
-            waker.wake();
+        for waker_slot in self.0.borrow_mut().drain(..) {
+            if let Some(waker) = waker_slot.take() {
+                waker.wake();
+            }
         }
     }
 }
@@ -0,0 +1,284 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+/// A counting semaphore for bounding concurrency within a shard.
+///
+/// Unlike [`crate::native::Gate`], which only tracks how many operations are
+/// in progress, a `Semaphore` lets callers wait until a requested number of
+/// units become available. Waiters are granted access in FIFO order: a large
+/// request that can't yet be satisfied blocks all smaller requests behind it,
+/// so nobody can starve the head of the queue.
+pub struct Semaphore {
+    available: Cell<usize>,
+    closed: Cell<bool>,
+    waiters: RefCell<VecDeque<Rc<Waiter>>>,
+}
+
+struct Waiter {
+    needed: usize,
+    waker: Cell<Option<Waker>>,
+    granted: Cell<bool>,
+    cancelled: Cell<bool>,
+}
+
+impl Semaphore {
+    /// Creates a new semaphore with `units` initially available.
+    #[inline]
+    pub fn new(units: usize) -> Self {
+        Self {
+            available: Cell::new(units),
+            closed: Cell::new(false),
+            waiters: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Returns the number of units currently available to be acquired.
+    #[inline]
+    pub fn available_units(&self) -> usize {
+        self.available.get()
+    }
+
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.closed.get()
+    }
+
+    /// Waits until `units` are available, then returns a guard that releases
+    /// them back to the semaphore on drop.
+    pub fn acquire(&self, units: usize) -> Acquire<'_> {
+        Acquire {
+            semaphore: self,
+            units,
+            waiter: None,
+        }
+    }
+
+    /// Attempts to acquire `units` without waiting. Fails if the units are
+    /// not immediately available or if the semaphore has been closed.
+    ///
+    /// To preserve FIFO fairness, this will not jump ahead of any already
+    /// parked waiters even if enough units happen to be free.
+    pub fn try_acquire(&self, units: usize) -> Result<SemaphorePermit<'_>, TryAcquireError> {
+        if self.is_closed() {
+            return Err(TryAcquireError::Closed);
+        }
+        if !self.waiters.borrow().is_empty() || self.available.get() < units {
+            return Err(TryAcquireError::InsufficientUnits);
+        }
+        self.available.set(self.available.get() - units);
+        Ok(SemaphorePermit {
+            semaphore: self,
+            units,
+        })
+    }
+
+    /// Closes the semaphore, waking every pending waiter with an
+    /// [`AcquireError`]. Permits already handed out continue to work as
+    /// usual, but any future call to [`Semaphore::acquire`] or
+    /// [`Semaphore::try_acquire`] will fail.
+    pub fn close(&self) {
+        self.closed.set(true);
+        for waiter in self.waiters.borrow_mut().drain(..) {
+            if let Some(waker) = waiter.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    fn release(&self, units: usize) {
+        self.available.set(self.available.get() + units);
+        self.wake_satisfiable_waiters();
+    }
+
+    // Walks the FIFO queue head-first, granting and waking every waiter
+    // whose request now fits, and stopping at the first one that doesn't -
+    // this is what keeps large requests from starving smaller ones instead
+    // of being starved by them.
+    fn wake_satisfiable_waiters(&self) {
+        let mut waiters = self.waiters.borrow_mut();
+        while let Some(front) = waiters.front() {
+            if front.cancelled.get() {
+                waiters.pop_front();
+                continue;
+            }
+            if front.needed > self.available.get() {
+                break;
+            }
+            let waiter = waiters.pop_front().unwrap();
+            self.available.set(self.available.get() - waiter.needed);
+            waiter.granted.set(true);
+            if let Some(waker) = waiter.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// A future returned by [`Semaphore::acquire`].
+pub struct Acquire<'a> {
+    semaphore: &'a Semaphore,
+    units: usize,
+    waiter: Option<Rc<Waiter>>,
+}
+
+impl<'a> Future for Acquire<'a> {
+    type Output = Result<SemaphorePermit<'a>, AcquireError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if let Some(waiter) = &this.waiter {
+            if this.semaphore.is_closed() && !waiter.granted.get() {
+                return Poll::Ready(Err(AcquireError));
+            }
+            if waiter.granted.get() {
+                return Poll::Ready(Ok(SemaphorePermit {
+                    semaphore: this.semaphore,
+                    units: this.units,
+                }));
+            }
+            waiter.waker.set(Some(cx.waker().clone()));
+            return Poll::Pending;
+        }
+
+        if this.semaphore.is_closed() {
+            return Poll::Ready(Err(AcquireError));
+        }
+
+        // Fast path: nothing is queued ahead of us and enough units are
+        // free right away.
+        if this.semaphore.waiters.borrow().is_empty() && this.semaphore.available.get() >= this.units {
+            this.semaphore
+                .available
+                .set(this.semaphore.available.get() - this.units);
+            return Poll::Ready(Ok(SemaphorePermit {
+                semaphore: this.semaphore,
+                units: this.units,
+            }));
+        }
+
+        let waiter = Rc::new(Waiter {
+            needed: this.units,
+            waker: Cell::new(Some(cx.waker().clone())),
+            granted: Cell::new(false),
+            cancelled: Cell::new(false),
+        });
+        this.semaphore.waiters.borrow_mut().push_back(Rc::clone(&waiter));
+        this.waiter = Some(waiter);
+        Poll::Pending
+    }
+}
+
+impl<'a> Drop for Acquire<'a> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            if !waiter.granted.get() {
+                waiter.cancelled.set(true);
+                // The cancelled waiter might have been blocking smaller
+                // requests behind it; re-run the grant walk now that it's
+                // out of the way.
+                self.semaphore.wake_satisfiable_waiters();
+            }
+        }
+    }
+}
+
+/// An RAII guard representing units held from a [`Semaphore`]. The units are
+/// returned to the semaphore when the guard is dropped.
+pub struct SemaphorePermit<'a> {
+    semaphore: &'a Semaphore,
+    units: usize,
+}
+
+impl<'a> SemaphorePermit<'a> {
+    /// Returns the number of units held by this permit.
+    #[inline]
+    pub fn units(&self) -> usize {
+        self.units
+    }
+}
+
+impl<'a> Drop for SemaphorePermit<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        self.semaphore.release(self.units);
+    }
+}
+
+/// Returned by [`Semaphore::acquire`] when the semaphore was closed while the
+/// caller was waiting.
+#[derive(Debug)]
+pub struct AcquireError;
+
+impl fmt::Display for AcquireError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the semaphore was closed while waiting to acquire units")
+    }
+}
+
+impl Error for AcquireError {}
+
+/// Returned by [`Semaphore::try_acquire`] when units could not be granted
+/// immediately.
+#[derive(Debug)]
+pub enum TryAcquireError {
+    /// Not enough units were available, or a waiter was already queued ahead
+    /// of this request.
+    InsufficientUnits,
+    /// The semaphore has been closed.
+    Closed,
+}
+
+impl fmt::Display for TryAcquireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryAcquireError::InsufficientUnits => write!(f, "not enough units are available"),
+            TryAcquireError::Closed => write!(f, "the semaphore is closed"),
+        }
+    }
+}
+
+impl Error for TryAcquireError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acquire_grants_in_fifo_order() {
+        let sem = Semaphore::new(1);
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let acquire1 = sem.acquire(1);
+        let acquire2 = sem.acquire(1);
+        let acquire3 = sem.acquire(1);
+        futures::pin_mut!(acquire1);
+        futures::pin_mut!(acquire2);
+        futures::pin_mut!(acquire3);
+
+        let permit1 = match acquire1.as_mut().poll(&mut cx) {
+            Poll::Ready(Ok(permit)) => permit,
+            _ => panic!("the only caller so far should be granted immediately"),
+        };
+        assert!(acquire2.as_mut().poll(&mut cx).is_pending());
+        assert!(acquire3.as_mut().poll(&mut cx).is_pending());
+
+        drop(permit1);
+        let permit2 = match acquire2.as_mut().poll(&mut cx) {
+            Poll::Ready(Ok(permit)) => permit,
+            _ => panic!("the second caller should be granted before the third"),
+        };
+        assert!(acquire3.as_mut().poll(&mut cx).is_pending());
+
+        drop(permit2);
+        assert!(acquire3.as_mut().poll(&mut cx).is_ready());
+    }
+}
@@ -153,7 +153,7 @@ where
 // Uninhabitable type
 enum Never {}
 
-async fn invoke_on_all_shards<Func, Fut, Ret>(f: Func) -> Vec<Ret>
+pub(crate) async fn invoke_on_all_shards<Func, Fut, Ret>(f: Func) -> Vec<Ret>
 where
     Func: FnOnce() -> Fut + Send + Clone + 'static,
     Fut: Future<Output = Ret> + 'static,
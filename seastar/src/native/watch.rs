@@ -0,0 +1,228 @@
+use std::cell::{Cell, Ref, RefCell};
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+/// Creates a shard-local "latest value wins" channel, returning a [`Sender`]
+/// and an initial [`Receiver`] seeing `initial` as the current value.
+///
+/// This complements [`crate::native::PhasedBarrier`] and
+/// [`crate::native::Gate`] as the standard way to broadcast configuration or
+/// state changes to any number of interested tasks on the shard: a single
+/// producer publishes values, and each consumer only ever observes the most
+/// recent one.
+pub fn channel<T>(initial: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Rc::new(Shared {
+        value: RefCell::new(initial),
+        version: Cell::new(0),
+        closed: Cell::new(false),
+        receiver_count: Cell::new(1),
+        wakers: RefCell::new(Vec::new()),
+    });
+    let receiver = Receiver {
+        shared: Rc::clone(&shared),
+        seen_version: 0,
+    };
+    (Sender { shared }, receiver)
+}
+
+struct Shared<T> {
+    value: RefCell<T>,
+    version: Cell<u64>,
+    closed: Cell<bool>,
+    receiver_count: Cell<usize>,
+    // Every pending `Changed` registers its waker slot here, once, the
+    // first time it's polled while still pending - subsequent polls of the
+    // same future just update the slot in place rather than pushing again.
+    wakers: RefCell<Vec<Rc<Cell<Option<Waker>>>>>,
+}
+
+impl<T> Shared<T> {
+    fn publish(&self) {
+        self.version.set(self.version.get() + 1);
+        wake_all(&self.wakers);
+    }
+}
+
+fn wake_all(wakers: &RefCell<Vec<Rc<Cell<Option<Waker>>>>>) {
+    for waker_slot in wakers.borrow_mut().drain(..) {
+        if let Some(waker) = waker_slot.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// The sending half of a [`channel`].
+pub struct Sender<T> {
+    shared: Rc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Replaces the current value and notifies every receiver waiting on
+    /// [`Receiver::changed`].
+    pub fn send(&self, value: T) {
+        *self.shared.value.borrow_mut() = value;
+        self.shared.publish();
+    }
+
+    /// Modifies the current value in place and notifies every receiver
+    /// waiting on [`Receiver::changed`].
+    pub fn send_modify<F>(&self, modify: F)
+    where
+        F: FnOnce(&mut T),
+    {
+        modify(&mut self.shared.value.borrow_mut());
+        self.shared.publish();
+    }
+
+    /// Returns the number of receivers currently alive, including ones
+    /// produced via [`Receiver::clone`].
+    #[inline]
+    pub fn receiver_count(&self) -> usize {
+        self.shared.receiver_count.get()
+    }
+
+    /// Returns `true` if every receiver has been dropped, meaning sent
+    /// values would have no observer.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.shared.receiver_count.get() == 0
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared.closed.set(true);
+        wake_all(&self.shared.wakers);
+    }
+}
+
+/// The receiving half of a [`channel`]. Cloning a `Receiver` creates another
+/// independent cursor over the same stream of values, starting from the
+/// value already observed by the original.
+pub struct Receiver<T> {
+    shared: Rc<Shared<T>>,
+    seen_version: u64,
+}
+
+impl<T> Receiver<T> {
+    /// Waits until the sender publishes a value newer than the last one this
+    /// receiver observed.
+    #[inline]
+    pub fn changed(&self) -> Changed<'_, T> {
+        Changed {
+            receiver: self,
+            waker_slot: None,
+        }
+    }
+
+    /// Marks the current value as seen and returns a reference to it.
+    pub fn borrow_and_update(&mut self) -> Ref<'_, T> {
+        self.seen_version = self.shared.version.get();
+        self.shared.value.borrow()
+    }
+
+    /// Returns a reference to the current value without marking it as seen.
+    #[inline]
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.shared.value.borrow()
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.shared
+            .receiver_count
+            .set(self.shared.receiver_count.get() + 1);
+        Self {
+            shared: Rc::clone(&self.shared),
+            seen_version: self.seen_version,
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared
+            .receiver_count
+            .set(self.shared.receiver_count.get() - 1);
+    }
+}
+
+/// A future returned by [`Receiver::changed`].
+pub struct Changed<'a, T> {
+    receiver: &'a Receiver<T>,
+    // Our own waker slot, once registered with `Shared::wakers`.
+    waker_slot: Option<Rc<Cell<Option<Waker>>>>,
+}
+
+impl<'a, T> Future for Changed<'a, T> {
+    type Output = Result<(), RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let shared = &this.receiver.shared;
+        if shared.version.get() > this.receiver.seen_version {
+            return Poll::Ready(Ok(()));
+        }
+        if shared.closed.get() {
+            return Poll::Ready(Err(RecvError));
+        }
+        match &this.waker_slot {
+            Some(waker_slot) => waker_slot.set(Some(cx.waker().clone())),
+            None => {
+                let waker_slot = Rc::new(Cell::new(Some(cx.waker().clone())));
+                shared.wakers.borrow_mut().push(Rc::clone(&waker_slot));
+                this.waker_slot = Some(waker_slot);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+/// Returned by [`Receiver::changed`] when the sender has been dropped and no
+/// further values will ever be published.
+#[derive(Debug)]
+pub struct RecvError;
+
+impl fmt::Display for RecvError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the watch sender was dropped")
+    }
+}
+
+impl Error for RecvError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_resolves_after_send_and_errors_after_sender_is_dropped() {
+        let (sender, mut receiver) = channel(0);
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let changed = receiver.changed();
+        futures::pin_mut!(changed);
+        assert!(changed.as_mut().poll(&mut cx).is_pending());
+
+        sender.send(1);
+        assert!(matches!(changed.as_mut().poll(&mut cx), Poll::Ready(Ok(()))));
+        assert_eq!(*receiver.borrow_and_update(), 1);
+
+        let changed = receiver.changed();
+        futures::pin_mut!(changed);
+        assert!(changed.as_mut().poll(&mut cx).is_pending());
+
+        drop(sender);
+        assert!(matches!(
+            changed.as_mut().poll(&mut cx),
+            Poll::Ready(Err(RecvError))
+        ));
+    }
+}
@@ -27,19 +27,28 @@ fn test_preempt_smoke_test() {
 
 #[inline]
 pub fn yield_now() -> YieldFuture {
-    YieldFuture { need_yield: true }
+    YieldFuture {
+        need_yield: true,
+        forced: false,
+    }
 }
 
 #[inline]
 pub fn maybe_yield() -> YieldFuture {
+    let forced = need_preempt();
     YieldFuture {
-        need_yield: need_preempt(),
+        need_yield: forced,
+        forced,
     }
 }
 
 #[derive(Debug)]
 pub struct YieldFuture {
     need_yield: bool,
+    // Whether this yield, if it happens, was actually due to `need_preempt`
+    // (as opposed to `yield_now`'s unconditional, voluntary yield) - only
+    // this kind should count against `forced_yields`.
+    forced: bool,
 }
 
 impl Future for YieldFuture {
@@ -49,6 +58,9 @@ impl Future for YieldFuture {
         let mut s = self.as_mut();
         if s.need_yield {
             s.need_yield = false;
+            if s.forced {
+                crate::metrics::record_forced_yield();
+            }
             cx.waker().wake_by_ref();
             Poll::Pending
         } else {
@@ -1,9 +1,13 @@
 use futures::channel::oneshot;
 use futures::{Future, FutureExt};
+use std::any::Any;
+use std::cell::RefCell;
 use std::ffi;
 use std::mem::ManuallyDrop;
 use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
 
 type RustFuturePollFn = unsafe extern "C" fn(*mut ffi::c_void, *mut ffi::c_void) -> ffi::c_int;
@@ -22,6 +26,13 @@ extern "C" {
         rust_future: *mut ffi::c_void,
         shard: ffi::c_uint,
     );
+    fn this_shard() -> ffi::c_uint;
+
+    // Asks the C++ side to make sure `rust_future` gets polled at least once
+    // more on `home_shard`, even if nothing else would otherwise wake it.
+    // This is how a cancellation requested from a different shard than the
+    // one the task lives on gets routed home before the future is torn down.
+    fn seastar_rs_request_cancel(rust_future: *mut ffi::c_void, home_shard: ffi::c_uint);
 }
 
 static WAKER_VTABLE: RawWakerVTable = RawWakerVTable::new(
@@ -49,17 +60,74 @@ where
     let waker = ManuallyDrop::new(Waker::from_raw(raw));
     let mut context = Context::from_waker(&waker);
 
-    // TODO: Handle panics
-    match Pin::new_unchecked(&mut *(fut as *mut Fut)).poll(&mut context) {
-        Poll::Pending => 0,
-        Poll::Ready(()) => {
+    crate::coop::reset_budget();
+
+    let poll_result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        Pin::new_unchecked(&mut *(fut as *mut Fut)).poll(&mut context)
+    }));
+
+    match poll_result {
+        Ok(Poll::Pending) => 0,
+        Ok(Poll::Ready(())) => {
             // Drop the future, then return
-            let _ = Box::from_raw(fut);
+            let _ = Box::from_raw(fut as *mut Fut);
+            1
+        }
+        Err(panic) => {
+            // An ordinary task panic never reaches here: spawn_impl and
+            // submit_to_impl already wrap the user's future in its own
+            // catch_unwind and ship the result through the oneshot channel.
+            // This is a last-resort backstop for a panic in our own glue
+            // code (this file, or a primitive it drives) - unwinding past
+            // this point would cross into C++, which is undefined
+            // behavior, so we must not let that happen either way.
+            //
+            // Free the task first so the reactor doesn't poll a
+            // half-torn-down future again. This also drops whatever oneshot
+            // sender the future was holding, which is what lets an
+            // awaiting `JoinHandle` resolve (to `None`) instead of hanging,
+            // under the `Propagate` policy.
+            let _ = Box::from_raw(fut as *mut Fut);
+            match panic_policy() {
+                PanicPolicy::Propagate => {}
+                PanicPolicy::Abort => {
+                    eprintln!("seastar-rs: a task panicked at the FFI poll boundary: {panic:?}");
+                    std::process::abort();
+                }
+            }
             1
         }
     }
 }
 
+/// Controls what happens when a task panics in a way that isn't already
+/// contained by `spawn`/`submit_to`'s own `catch_unwind` (i.e. a bug in the
+/// runtime's own glue code, rather than in user task code).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// Tear the task down and let any awaiting `JoinHandle` observe it as a
+    /// cancellation, leaving the rest of the shard running. The default.
+    Propagate,
+    /// Abort the process, on the assumption that a panic here means the
+    /// runtime's invariants can no longer be trusted.
+    Abort,
+}
+
+static PANIC_POLICY: std::sync::atomic::AtomicU8 = std::sync::atomic::AtomicU8::new(0);
+
+/// Sets the policy applied when a task panics at the FFI poll boundary. See
+/// [`PanicPolicy`].
+pub fn set_panic_policy(policy: PanicPolicy) {
+    PANIC_POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+fn panic_policy() -> PanicPolicy {
+    match PANIC_POLICY.load(Ordering::Relaxed) {
+        1 => PanicPolicy::Abort,
+        _ => PanicPolicy::Propagate,
+    }
+}
+
 fn get_poll_fn<Fut>(_f: &Fut) -> RustFuturePollFn
 where
     Fut: Future<Output = ()> + 'static,
@@ -67,49 +135,351 @@ where
     poll_fn::<Fut>
 }
 
-pub(crate) fn spawn_impl<Fut, Ret>(fut: Fut) -> impl Future<Output = Ret>
+// Shared between a task and the `JoinHandle` that can cancel or inspect it.
+// Lives in an `Arc` because cancellation may be requested from a shard other
+// than the one the task is running on.
+#[derive(Default)]
+struct TaskState {
+    cancel_requested: AtomicBool,
+    finished: AtomicBool,
+    // The boxed `Cancellable<Fut>` passed to the FFI spawn functions, or null
+    // once the task has settled (about to be freed by `poll_fn`'s cleanup, or
+    // already claimed by `JoinHandle::cancel`). Whichever of `cancel` or the
+    // task's own final poll swaps this to null first is the only one allowed
+    // to touch the pointer, which is what keeps `cancel` from ever handing a
+    // dangling pointer to `seastar_rs_request_cancel`: the task's final poll
+    // always clears it before returning control to `poll_fn`, i.e. strictly
+    // before the box is freed.
+    fut_ptr: AtomicPtr<ffi::c_void>,
+}
+
+thread_local! {
+    // A stack rather than a single slot because a task's poll can, in
+    // principle, drive a nested executor that polls another task inline.
+    static CURRENT_TASK_METADATA: RefCell<Vec<TaskMetadata>> = RefCell::new(Vec::new());
+}
+
+/// User-attached metadata for a spawned task: a name plus an optional small
+/// typed payload (a scheduling-group id, a correlation id, ...), readable
+/// for the whole lifetime of the task via [`current_task_metadata`].
+///
+/// Build one with [`TaskMetadata::new`] and pass it to
+/// [`crate::spawn_with`]/[`crate::submit_to_with`].
+#[derive(Clone, Default)]
+pub struct TaskMetadata {
+    name: Option<&'static str>,
+    payload: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl TaskMetadata {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    #[inline]
+    pub fn with_payload<T: Any + Send + Sync>(mut self, payload: T) -> Self {
+        self.payload = Some(Arc::new(payload));
+        self
+    }
+
+    #[inline]
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    pub fn payload<T: Any>(&self) -> Option<&T> {
+        self.payload.as_deref()?.downcast_ref()
+    }
+}
+
+/// Returns the metadata attached to the task currently being polled, if any.
+///
+/// Intended for things like log lines that want to automatically annotate
+/// themselves with the name of the running task, or a future debug
+/// subsystem enumerating in-flight tasks per shard.
+pub fn current_task_metadata() -> Option<TaskMetadata> {
+    CURRENT_TASK_METADATA.with(|stack| stack.borrow().last().cloned())
+}
+
+// Wraps the task's future so that, once cancellation is requested, the next
+// poll drops the inner future in place (running its destructors) and
+// resolves immediately instead of polling it again. This reuses the
+// existing `poll_fn`/`Box::from_raw` cleanup path: from its point of view
+// nothing is different about a cancelled task finishing.
+//
+// Also makes this task's metadata observable via `current_task_metadata`
+// for as long as it's being polled.
+struct Cancellable<Fut> {
+    inner: Option<Fut>,
+    state: Arc<TaskState>,
+    metadata: TaskMetadata,
+    // Whether `record_task_spawned()` has fired yet. Deferred to the first
+    // poll (rather than done eagerly in `spawn_cancellable`) so that for
+    // `submit_to`, which constructs this on the origin shard but only ever
+    // polls it once it's landed on the target shard, the spawn accounting
+    // happens on the same shard as `record_task_finished()` later does.
+    spawn_recorded: bool,
+}
+
+impl<Fut> Future for Cancellable<Fut>
+where
+    Fut: Future<Output = ()>,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = unsafe { self.get_unchecked_mut() };
+        if !this.spawn_recorded {
+            this.spawn_recorded = true;
+            crate::metrics::record_task_spawned();
+        }
+        if this.state.cancel_requested.load(Ordering::Acquire) {
+            this.inner = None;
+            // Claim `fut_ptr` before we report ourselves finished: this is
+            // what `poll_fn` will free next, so from this point on
+            // `JoinHandle::cancel` must not be handed it.
+            this.state.fut_ptr.swap(std::ptr::null_mut(), Ordering::AcqRel);
+            crate::metrics::record_task_finished();
+            return Poll::Ready(());
+        }
+        CURRENT_TASK_METADATA.with(|stack| stack.borrow_mut().push(this.metadata.clone()));
+        let res = match &mut this.inner {
+            // SAFETY: `this.inner` is only ever moved out of above, which
+            // also makes this branch unreachable afterwards.
+            Some(fut) => unsafe { Pin::new_unchecked(fut) }.poll(cx),
+            None => Poll::Ready(()),
+        };
+        CURRENT_TASK_METADATA.with(|stack| {
+            stack.borrow_mut().pop();
+        });
+        if res.is_ready() {
+            this.state.fut_ptr.swap(std::ptr::null_mut(), Ordering::AcqRel);
+        }
+        res
+    }
+}
+
+fn spawn_cancellable<Fut, Ret>(
+    fut: Fut,
+    metadata: TaskMetadata,
+) -> (
+    Cancellable<impl Future<Output = ()>>,
+    oneshot::Receiver<std::thread::Result<Ret>>,
+    Arc<TaskState>,
+)
 where
     Fut: Future<Output = Ret> + 'static,
     Ret: 'static,
 {
     // TODO: Use a non-thread-safe version of the channel
     let (sender, receiver) = oneshot::channel();
-    let fut = async move {
-        let _ = sender.send(AssertUnwindSafe(fut).catch_unwind().await);
+    let state = Arc::new(TaskState::default());
+    let task_state = Arc::clone(&state);
+    let inner = async move {
+        let result = AssertUnwindSafe(fut).catch_unwind().await;
+        task_state.finished.store(true, Ordering::Release);
+        crate::metrics::record_task_finished();
+        let _ = sender.send(result);
     };
-    let poll_fn = get_poll_fn(&fut);
-    let fut_ptr = Box::into_raw(Box::new(fut)) as *mut ffi::c_void;
+    (
+        Cancellable {
+            inner: Some(inner),
+            state: Arc::clone(&state),
+            metadata,
+            spawn_recorded: false,
+        },
+        receiver,
+        state,
+    )
+}
+
+pub(crate) fn spawn_impl<Fut, Ret>(fut: Fut) -> JoinHandle<Ret>
+where
+    Fut: Future<Output = Ret> + 'static,
+    Ret: 'static,
+{
+    spawn_with_impl(fut, TaskMetadata::new())
+}
+
+pub(crate) fn spawn_with_impl<Fut, Ret>(fut: Fut, metadata: TaskMetadata) -> JoinHandle<Ret>
+where
+    Fut: Future<Output = Ret> + 'static,
+    Ret: 'static,
+{
+    let (cancellable, receiver, state) = spawn_cancellable(fut, metadata);
+    let poll_fn = get_poll_fn(&cancellable);
+    let fut_ptr = Box::into_raw(Box::new(cancellable)) as *mut ffi::c_void;
+    state.fut_ptr.store(fut_ptr, Ordering::Release);
     unsafe {
         seastar_rs_spawn(poll_fn, fut_ptr);
     }
-    async move {
-        match receiver.await.unwrap() {
-            Ok(v) => v,
-            Err(err) => std::panic::resume_unwind(err),
-        }
+    JoinHandle {
+        receiver,
+        settled: None,
+        state,
+        home_shard: unsafe { this_shard() },
     }
 }
 
-pub(crate) fn submit_to_impl<Func, Fut, Ret>(shard: u32, func: Func) -> impl Future<Output = Ret>
+pub(crate) fn submit_to_impl<Func, Fut, Ret>(shard: u32, func: Func) -> JoinHandle<Ret>
 where
     Func: FnOnce() -> Fut + Send + 'static,
     Fut: Future<Output = Ret> + 'static,
     Ret: Send + 'static,
 {
-    let (sender, receiver) = oneshot::channel();
-    let fut = async move {
-        let fut = func();
-        let _ = sender.send(AssertUnwindSafe(fut).catch_unwind().await);
-    };
-    let poll_fn = get_poll_fn(&fut);
-    let fut_ptr = Box::into_raw(Box::new(fut)) as *mut ffi::c_void;
+    submit_to_with_impl(shard, func, TaskMetadata::new())
+}
+
+pub(crate) fn submit_to_with_impl<Func, Fut, Ret>(
+    shard: u32,
+    func: Func,
+    metadata: TaskMetadata,
+) -> JoinHandle<Ret>
+where
+    Func: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = Ret> + 'static,
+    Ret: Send + 'static,
+{
+    crate::metrics::record_submit_to_sent();
+    let (cancellable, receiver, state) = spawn_cancellable(
+        async move {
+            crate::metrics::record_submit_to_received();
+            func().await
+        },
+        metadata,
+    );
+    let poll_fn = get_poll_fn(&cancellable);
+    let fut_ptr = Box::into_raw(Box::new(cancellable)) as *mut ffi::c_void;
+    state.fut_ptr.store(fut_ptr, Ordering::Release);
     unsafe {
         seastar_rs_submit_to(poll_fn, fut_ptr, shard as ffi::c_uint);
     }
-    async move {
-        match receiver.await.unwrap() {
-            Ok(v) => v,
-            Err(err) => std::panic::resume_unwind(err),
+    JoinHandle {
+        receiver,
+        settled: None,
+        state,
+        home_shard: shard as ffi::c_uint,
+    }
+}
+
+/// A handle to a task spawned via `spawn`/`submit_to`.
+///
+/// Awaiting the handle yields `Some(Ret)` once the task completes, or `None`
+/// if it was cancelled first. Dropping the handle without cancelling simply
+/// detaches it: the task keeps running on the reactor to completion, and its
+/// result is discarded.
+pub struct JoinHandle<Ret> {
+    receiver: oneshot::Receiver<std::thread::Result<Ret>>,
+    // Caches the receiver's outcome once it resolves, for as long as
+    // `coop::poll_proceed` withholds it - the receiver can only hand its
+    // value over once, so we can't just re-poll it next time around.
+    settled: Option<Result<std::thread::Result<Ret>, oneshot::Canceled>>,
+    state: Arc<TaskState>,
+    home_shard: ffi::c_uint,
+}
+
+impl<Ret> JoinHandle<Ret> {
+    /// Requests that the task be cancelled. The underlying future is dropped
+    /// in place (without being polled again) the next time it is polled,
+    /// which may happen after this call returns - awaiting the handle is the
+    /// only way to know cancellation has actually taken effect.
+    pub fn cancel(&self) {
+        self.state.cancel_requested.store(true, Ordering::Release);
+        // Claim `fut_ptr` for ourselves before touching it. The task's own
+        // final poll clears it the same way before yielding control back to
+        // `poll_fn` for cleanup, strictly before the box is freed - so
+        // whichever of the two sides performs this swap first is guaranteed
+        // the box is still alive for the duration of its branch, and the
+        // other side sees null and does nothing. This is what makes it safe
+        // to race `cancel()` against a task that's finishing on its own.
+        let fut_ptr = self.state.fut_ptr.swap(std::ptr::null_mut(), Ordering::AcqRel);
+        if !fut_ptr.is_null() {
+            unsafe {
+                seastar_rs_request_cancel(fut_ptr, self.home_shard);
+            }
+        }
+    }
+
+    /// Detaches the handle, letting the task run to completion in the
+    /// background with its result discarded.
+    #[inline]
+    pub fn detach(self) {}
+
+    /// Returns `true` once the task has produced a value (or panicked).
+    /// Does not reflect cancellation: a cancelled task that is dropped
+    /// in place is only observed via the handle resolving to `None`.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.state.finished.load(Ordering::Acquire)
+    }
+}
+
+impl<Ret> Future for JoinHandle<Ret> {
+    type Output = Option<Ret>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if this.settled.is_none() {
+            this.settled = match Pin::new(&mut this.receiver).poll(cx) {
+                Poll::Ready(res) => Some(res),
+                Poll::Pending => return Poll::Pending,
+            };
+        }
+
+        // This is the leaf of a `submit_to`/task-completion wait, so it's
+        // one of the spots the cooperative budget is meant to bound - see
+        // `coop::poll_proceed`. Unlike the pattern in its doc comment, the
+        // receiver is polled first and its outcome kept in `settled` while
+        // the budget withholds it.
+        let coop = match crate::coop::poll_proceed(cx) {
+            Poll::Ready(coop) => coop,
+            Poll::Pending => return Poll::Pending,
+        };
+        coop.made_progress();
+
+        match this.settled.take().unwrap() {
+            Ok(Ok(v)) => Poll::Ready(Some(v)),
+            Ok(Err(panic)) => std::panic::resume_unwind(panic),
+            // The sender was dropped without sending, which happens when
+            // the task was cancelled before it produced a value.
+            Err(_canceled) => Poll::Ready(None),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Exercises `Cancellable` and `spawn_cancellable` directly, bypassing
+    // `spawn_impl`/`submit_to_impl` (and the FFI calls they make) entirely -
+    // this logic is plain `std`/`futures` and needs no Seastar runtime.
+    #[test]
+    fn cancelling_before_completion_drops_inner_and_closes_the_receiver() {
+        let (cancellable, mut receiver, state) =
+            spawn_cancellable(futures::future::pending::<()>(), TaskMetadata::new());
+        futures::pin_mut!(cancellable);
+
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert!(cancellable.as_mut().poll(&mut cx).is_pending());
+
+        state.cancel_requested.store(true, Ordering::Release);
+        assert_eq!(cancellable.as_mut().poll(&mut cx), Poll::Ready(()));
+
+        // Cancellation doesn't report the task as finished - only actually
+        // running to completion does.
+        assert!(!state.finished.load(Ordering::Acquire));
+        // Dropping `inner` in place dropped its captured oneshot sender too,
+        // so the receiver observes the task is gone rather than hanging.
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -2,9 +2,11 @@ use futures::channel::{mpsc, oneshot};
 use futures::{Future, FutureExt, SinkExt, StreamExt};
 use std::panic::{resume_unwind, AssertUnwindSafe, UnwindSafe};
 use std::pin::Pin;
+use std::rc::Rc;
 use std::sync::{Mutex, PoisonError};
 use std::thread::JoinHandle;
 
+use crate::native::gate::Gate;
 use crate::AppTemplate;
 
 struct TestCase {
@@ -24,17 +26,29 @@ impl SeastarTestRunner {
         let (test_sender, mut test_receiver) = mpsc::channel::<TestCase>(1);
         let join_handle = std::thread::spawn(move || {
             AppTemplate::default().run_void(std::env::args().take(1), async move {
-                // We will block while waiting for the next test, but that's ok.
-                // After receiving a test we poll it to completion, then wait
-                // for the next test. There isn't anything useful to do in the
-                // meantime.
-                //
-                // This doesn't allow for multiple concurrent tests yet,
-                // unfortunately.
-                while let Some(tc) = futures::executor::block_on(test_receiver.next()) {
-                    let res = (tc.fun)().catch_unwind().await;
-                    let _ = tc.result_sender.send(res);
+                // Every in-flight test holds the gate open; once the sender
+                // side is dropped (run_test() has nothing left to send) we
+                // close it and wait for whatever is still running, instead
+                // of serializing the whole suite through a single slot.
+                let gate = Rc::new(Gate::new(()));
+                while let Some(tc) = test_receiver.next().await {
+                    let holder = match gate.enter_owned() {
+                        Ok(holder) => holder,
+                        Err(_) => {
+                            // Shutting down; nothing to do with this test case.
+                            continue;
+                        }
+                    };
+                    // spawn_impl dispatches onto the reactor right away, so
+                    // we don't need to do anything with the returned future
+                    // here - the result is delivered through result_sender.
+                    let _ = crate::spawn(async move {
+                        let _holder = holder;
+                        let res = (tc.fun)().catch_unwind().await;
+                        let _ = tc.result_sender.send(res);
+                    });
                 }
+                gate.close().await;
                 Ok(())
             });
         });
@@ -49,25 +63,31 @@ impl SeastarTestRunner {
         std::mem::drop(self.test_sender);
         self.join_handle.join().unwrap();
     }
+}
 
-    fn run_test<T, F>(&mut self, test: T) -> std::thread::Result<()>
-    where
-        T: FnOnce() -> F,
-        T: Send + 'static,
-        F: Future<Output = ()> + 'static,
-    {
-        let (sender, receiver) = oneshot::channel();
-        let fun = Box::new(move || {
-            Box::pin(AssertUnwindSafe(test()))
-                as Pin<Box<dyn Future<Output = ()> + UnwindSafe + 'static>>
-        });
-        let tc = TestCase {
-            fun,
-            result_sender: sender,
-        };
-        futures::executor::block_on(self.test_sender.send(tc)).unwrap();
-        futures::executor::block_on(receiver.map(Result::unwrap))
-    }
+// Sends `test` to the reactor thread and blocks until it's done running.
+// Takes an owned, already-cloned `test_sender` rather than borrowing the
+// runner, so the caller doesn't need to hold `TEST_RUNNER`'s lock for this
+// (possibly long) round trip - otherwise every `#[seastar::test]` thread
+// would serialize through a single slot regardless of how many the reactor
+// could actually run at once.
+fn submit_test<T, F>(mut test_sender: mpsc::Sender<TestCase>, test: T) -> std::thread::Result<()>
+where
+    T: FnOnce() -> F,
+    T: Send + 'static,
+    F: Future<Output = ()> + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    let fun = Box::new(move || {
+        Box::pin(AssertUnwindSafe(test()))
+            as Pin<Box<dyn Future<Output = ()> + UnwindSafe + 'static>>
+    });
+    let tc = TestCase {
+        fun,
+        result_sender: sender,
+    };
+    futures::executor::block_on(test_sender.send(tc)).unwrap();
+    futures::executor::block_on(receiver.map(Result::unwrap))
 }
 
 pub fn run_test<T, F>(test: T)
@@ -84,10 +104,13 @@ where
             libc::atexit(stop_runner);
         }
     }
-    let res = runtime.run_test(test);
-    // Drop the lock now, because we might rethrow the panic
+    let test_sender = runtime.test_sender.clone();
+    // Drop the lock before the blocking round trip below: it only guards
+    // initializing/tearing down the shared runner, not individual test
+    // runs.
     std::mem::drop(lock);
-    if let Err(p) = res {
+
+    if let Err(p) = submit_test(test_sender, test) {
         resume_unwind(p);
     }
 }